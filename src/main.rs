@@ -1,16 +1,41 @@
 extern crate argparse;
+extern crate md5;
+extern crate rayon;
+extern crate sha2;
+extern crate unidecode;
 
-use std::collections::{HashMap, hash_map::DefaultHasher};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet, hash_map::DefaultHasher};
 use std::hash::BuildHasherDefault;
 use std::fs::File;
 use std::path::Path;
 use std::io::{self, prelude::*};
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use unidecode::unidecode;
+
+// The default key representation: a sorted, deduplication-free list of the
+// letters (and digits) a word is made of. `subtract` does an O(n) merge-walk
+// of two sorted slices, which is simple but sits deep in the hot path of
+// `anagrams_recur`.
+#[cfg(not(feature = "simd-keys"))]
+type Key = Box<[u8]>;
+
+#[cfg(not(feature = "simd-keys"))]
+fn key_from_bytes(bytes: &[u8]) -> Key {
+    let mut bs = bytes.to_vec();
+    bs.sort();
+    bs.into_boxed_slice()
+}
 
-fn subtract(word: &[u8], pool: &[u8]) -> Option<Box<[u8]>> {
+#[cfg(not(feature = "simd-keys"))]
+fn subtract(word: &Key, pool: &Key) -> Option<Key> {
     let mut i = 0;
     let mut result = vec![];
 
-    for &c in pool {
+    for &c in pool.iter() {
         if i < word.len() && word[i] == c {
             i += 1;
         } else {
@@ -24,9 +49,88 @@ fn subtract(word: &[u8], pool: &[u8]) -> Option<Box<[u8]>> {
     None
 }
 
-fn make_key(word: &str) -> Option<Box<[u8]>> {
-    let mut bs: Vec<u8> = word.into();
-    if bs.iter().any(|&i| i >= 0x80) { return None }
+#[cfg(not(feature = "simd-keys"))]
+fn key_len(key: &Key) -> usize {
+    key.len()
+}
+
+// The "simd-keys" backend: every key (and the working pool) is a fixed-length
+// histogram of letter/digit counts instead of a sorted byte list. `subtract`
+// becomes a fixed-length lane-wise subtract that gives up at the first lane
+// where the word needs more than the pool holds, instead of the merge-walk
+// above, and avoids `anagrams_recur` allocating a fresh `Vec` per candidate.
+// 26 letters + 10 digits, plus one catch-all lane for anything else. The
+// catch-all exists so that passing un-normalized bytes (a caller skipping
+// `normalized_bytes`) degrades to "doesn't match anything" here, the same as
+// it does on the default backend, instead of panicking.
+#[cfg(feature = "simd-keys")]
+const KEY_LANES: usize = 37;
+
+#[cfg(feature = "simd-keys")]
+const OTHER_LANE: usize = KEY_LANES - 1;
+
+#[cfg(feature = "simd-keys")]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct KeyVec([u8; KEY_LANES]);
+
+#[cfg(feature = "simd-keys")]
+impl KeyVec {
+    fn lane(b: u8) -> usize {
+        match b {
+            b'a' ..= b'z' => (b - b'a') as usize,
+            b'0' ..= b'9' => 26 + (b - b'0') as usize,
+            _ => OTHER_LANE,
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.0.iter().map(|&c| c as usize).sum()
+    }
+
+    fn checked_sub(&self, other: &KeyVec) -> Option<KeyVec> {
+        let mut result = [0u8; KEY_LANES];
+        for i in 0..KEY_LANES {
+            if other.0[i] > self.0[i] {
+                return None
+            }
+            result[i] = self.0[i] - other.0[i];
+        }
+        Some(KeyVec(result))
+    }
+}
+
+#[cfg(feature = "simd-keys")]
+type Key = KeyVec;
+
+#[cfg(feature = "simd-keys")]
+fn key_from_bytes(bytes: &[u8]) -> Key {
+    let mut counts = [0u8; KEY_LANES];
+    for &b in bytes {
+        let lane = KeyVec::lane(b);
+        counts[lane] = counts[lane].saturating_add(1);
+    }
+    KeyVec(counts)
+}
+
+#[cfg(feature = "simd-keys")]
+fn subtract(word: &Key, pool: &Key) -> Option<Key> {
+    pool.checked_sub(word)
+}
+
+#[cfg(feature = "simd-keys")]
+fn key_len(key: &Key) -> usize {
+    key.total()
+}
+
+// Fold non-ASCII input to its closest ASCII spelling (e.g. "café" -> "cafe",
+// "Müller" -> "Muller") so accented dictionaries and query strings key the
+// same as their unaccented equivalents.
+fn transliterate(word: &str) -> String {
+    unidecode(word)
+}
+
+fn normalized_bytes(word: &str) -> Vec<u8> {
+    let mut bs: Vec<u8> = transliterate(word).into_bytes();
 
     // Take only the alphabetic characters
     let mut i = 0;
@@ -46,12 +150,98 @@ fn make_key(word: &str) -> Option<Box<[u8]>> {
         }
     }
 
-    bs.sort();
-    Some(bs.into_boxed_slice())
+    bs
+}
+
+fn make_key(word: &str) -> Key {
+    key_from_bytes(&normalized_bytes(word))
+}
+
+// Transliterated, lowercased, whitespace-collapsed form of a query phrase,
+// used to compare a candidate result against the literal input it was
+// searched from (see `AnagramType::Proper`).
+fn normalized_phrase(phrase: &str) -> String {
+    transliterate(phrase).to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Controls which candidate word sets `find_anagrams` considers a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnagramType<'a> {
+    /// Every letter of the query must be used by the result. This is the
+    /// original, default behavior.
+    Exact,
+    /// Like `Exact`, but a result made up of exactly `query`'s words (in
+    /// any order) is excluded.
+    Proper { query: &'a str },
+    /// The query is treated as an upper bound rather than an exact amount:
+    /// a result may leave letters of the pool unused, as long as it uses at
+    /// least `min_letters_used` of them.
+    Loose { min_letters_used: usize },
 }
 
-type Dictionary = HashMap<Box<[u8]>, Vec<Box<str>>, BuildHasherDefault<DefaultHasher>>;
-type Iter<'a> = std::collections::hash_map::Iter<'a, Box<[u8]>, Vec<Box<str>>>;
+/// Digest algorithm to match rendered phrases against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha256,
+}
+
+fn hash_phrase(phrase: &str, algorithm: HashAlgorithm) -> String {
+    match algorithm {
+        HashAlgorithm::Md5 => format!("{:x}", md5::compute(phrase.as_bytes())),
+        HashAlgorithm::Sha256 => format!("{:x}", Sha256::digest(phrase.as_bytes())),
+    }
+}
+
+/// Bundles `find_anagrams_matching_hashes`'s options so the method doesn't
+/// accumulate an unwieldy parameter list.
+pub struct HashMatch<'a> {
+    pub targets: &'a [String],
+    pub algorithm: HashAlgorithm,
+    pub try_permutations: bool,
+}
+
+fn permutations<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+    if items.is_empty() {
+        return vec![vec![]];
+    }
+
+    let mut result = vec![];
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let item = rest.remove(i);
+
+        for mut perm in permutations(&rest) {
+            perm.insert(0, item.clone());
+            result.push(perm);
+        }
+    }
+    result
+}
+
+type Dictionary = HashMap<Key, Vec<Box<str>>, BuildHasherDefault<DefaultHasher>>;
+
+// A snapshot of the dictionary's entries in a fixed order, paired with a
+// lookup from key to its position in that order. Recursion walks entries by
+// index instead of cloning a `hash_map::Iter`, so the "don't re-emit
+// permutations" check below can compare positions directly instead of
+// relying on the iteration order implied by entry addresses -- which is
+// what makes it safe to split the top level of the walk across threads.
+type Entries<'a> = Vec<(Key, &'a [Box<str>])>;
+type KeyIndex = HashMap<Key, usize>;
+
+// Everything `anagrams_recur` needs that stays the same across its whole
+// recursion, bundled together so the recursive call isn't a long run of
+// same-typed positional parameters (several of which are `usize`, easy to
+// transpose by accident).
+#[derive(Clone, Copy)]
+struct SearchContext<'a> {
+    entries: &'a Entries<'a>,
+    key_index: &'a KeyIndex,
+    anagram_type: AnagramType<'a>,
+    total_letters: usize,
+    min_letters_used: usize,
+}
 
 pub struct Anagrammer {
     dictionary: Dictionary,
@@ -69,8 +259,11 @@ impl Anagrammer {
             let word = line.trim();
             if word.len() == 0 { continue }
 
-            if let Some(bs) = make_key(&word) {
-                dictionary.entry(bs).or_insert_with(|| vec![]).push(word.into());
+            let word = transliterate(word);
+            let bs = make_key(&word);
+            let words = dictionary.entry(bs).or_insert_with(|| vec![]);
+            if !words.iter().any(|w| w.as_ref() == word) {
+                words.push(word.into_boxed_str());
             }
         }
 
@@ -85,9 +278,12 @@ impl Anagrammer {
         for line in include_str!("english-words").split('\n') {
             let w = line.trim().to_lowercase();
             if w.len() == 0 { continue }
-            
-            if let Some(bs) = make_key(&w) {
-                dictionary.entry(bs).or_insert_with(|| vec![]).push(w.into_boxed_str());
+
+            let w = transliterate(&w);
+            let bs = make_key(&w);
+            let words = dictionary.entry(bs).or_insert_with(|| vec![]);
+            if !words.iter().any(|word| word.as_ref() == w) {
+                words.push(w.into_boxed_str());
             }
         }
 
@@ -96,7 +292,7 @@ impl Anagrammer {
         }
     }
 
-    fn restrict(&mut self, pool: &[u8]) {
+    fn restrict(&mut self, pool: &Key) {
         self.dictionary.retain(|key, _| {
             subtract(key, pool).is_some()
         });
@@ -104,53 +300,220 @@ impl Anagrammer {
 
     pub fn restrict_letters(&mut self, minletters: usize, maxletters: usize) {
         self.dictionary.retain(|key, _| {
-            key.len() >= minletters && key.len() <= maxletters
+            let n = key_len(key);
+            n >= minletters && n <= maxletters
+        });
+    }
+
+    fn entries(&self) -> (Entries<'_>, KeyIndex) {
+        let entries: Entries = self.dictionary.iter().map(|(k, v)| (k.clone(), v.as_slice())).collect();
+        let key_index: KeyIndex = entries.iter().enumerate().map(|(i, (k, _))| (k.clone(), i)).collect();
+        (entries, key_index)
+    }
+
+    pub fn find_anagrams<F: FnMut(Vec<&str>)>(mut self, word: &[u8], minwords: usize, maxwords: usize, anagram_type: AnagramType, mut f: F) {
+        let pool = key_from_bytes(word);
+        self.restrict(&pool);
+        let (entries, key_index) = self.entries();
+        let total_letters = key_len(&pool);
+
+        let min_letters_used = match anagram_type {
+            AnagramType::Loose { min_letters_used } => min_letters_used,
+            _ => 0,
+        };
+        let normalized_query = match anagram_type {
+            AnagramType::Proper { query } => Some(normalized_phrase(query)),
+            _ => None,
+        };
+        // Sorted so that excluding a match is insensitive to word order --
+        // `query` is a set of words, not a phrase, as far as `Proper` cares.
+        let normalized_query_words: Option<Vec<&str>> = normalized_query.as_ref().map(|q| {
+            let mut words: Vec<&str> = q.split(' ').filter(|w| !w.is_empty()).collect();
+            words.sort_unstable();
+            words
+        });
+
+        let ctx = SearchContext { entries: &entries, key_index: &key_index, anagram_type, total_letters, min_letters_used };
+        self.anagrams_recur(ctx, 0, &pool, minwords, maxwords, &mut |set| {
+            if let Some(ref query_words) = normalized_query_words {
+                let mut candidate: Vec<String> = set.iter().map(|w| w.to_lowercase()).collect();
+                candidate.sort_unstable();
+                if candidate == *query_words {
+                    return
+                }
+            }
+            f(set);
         });
     }
 
-    pub fn find_anagrams<F: FnMut(Vec<&str>)>(mut self, word: &[u8], minwords: usize, maxwords: usize, mut f: F) {
-        let mut pool = word.iter().cloned().collect::<Vec<_>>();
-        pool.sort();
+    /// Like `find_anagrams`, but partitions the top level of the search
+    /// across a rayon thread pool: each dictionary entry starts its own
+    /// independent (single-threaded) recursion on a worker thread. Because
+    /// results can arrive out of order and from multiple threads at once,
+    /// this collects owned word sets into a shared sink instead of driving
+    /// an `FnMut` callback.
+    pub fn find_anagrams_parallel(mut self, word: &[u8], minwords: usize, maxwords: usize) -> Vec<Vec<Box<str>>> {
+        let pool = key_from_bytes(word);
         self.restrict(&pool);
-        self.anagrams_recur(self.dictionary.iter(), &pool, minwords, maxwords, &mut f);
+        let (entries, key_index) = self.entries();
+
+        let results: Mutex<Vec<Vec<Box<str>>>> = Mutex::new(vec![]);
+        let total_letters = key_len(&pool);
+        let ctx = SearchContext { entries: &entries, key_index: &key_index, anagram_type: AnagramType::Exact, total_letters, min_letters_used: 0 };
+
+        if key_len(&pool) == 0 && minwords == 0 {
+            results.lock().unwrap().push(vec![]);
+        }
+
+        if maxwords > 0 && minwords <= maxwords {
+            (0..entries.len()).into_par_iter().for_each(|idx| {
+                let (key, words) = &entries[idx];
+                if let Some(new_pool) = subtract(key, &pool) {
+                    let new_minwords = if minwords == 0 { 0 } else { minwords - 1 };
+                    self.anagrams_recur(ctx, idx + 1, &new_pool, new_minwords, maxwords - 1, &mut |set| {
+                        for word in words.iter() {
+                            let mut owned: Vec<Box<str>> = set.iter().map(|&w| Box::from(w)).collect();
+                            owned.push(word.clone());
+                            results.lock().unwrap().push(owned);
+                        }
+                    });
+                }
+            });
+        }
+
+        results.into_inner().unwrap()
+    }
+
+    /// Like `find_anagrams`, but only invokes `f` on word sets whose
+    /// space-joined phrase hashes to one of `hash_match.targets` (hex
+    /// digests) under `hash_match.algorithm`. Unless
+    /// `hash_match.try_permutations` is set, only the recursion's natural
+    /// ordering of each candidate set is checked -- cheap, but it means a
+    /// target phrase made of the same words in a different order will be
+    /// missed. With `try_permutations`, every ordering of a set is
+    /// hashed and checked instead (bounded by `minwords`/`maxwords`, since a
+    /// set's word count is what the permutation cost scales with), except
+    /// that sets larger than `MAX_BIGRAM_PERMUTE_WORDS` still fall back to
+    /// the natural order only, the same cutoff `LanguageModel::score_set`
+    /// uses for the same reason: `n!` stops being feasible long before a
+    /// user would notice the search hanging.
+    pub fn find_anagrams_matching_hashes<F: FnMut(Vec<&str>)>(
+        self,
+        word: &[u8],
+        minwords: usize,
+        maxwords: usize,
+        hash_match: HashMatch,
+        mut f: F,
+    ) {
+        let HashMatch { targets, algorithm, try_permutations } = hash_match;
+        let targets: HashSet<String> = targets.iter().map(|d| d.to_lowercase()).collect();
+
+        self.find_anagrams(word, minwords, maxwords, AnagramType::Exact, |set| {
+            let words: Vec<&str> = set.iter().cloned().rev().collect();
+
+            if try_permutations && words.len() <= MAX_BIGRAM_PERMUTE_WORDS {
+                for perm in permutations(&words) {
+                    let phrase = perm.join(" ");
+                    if targets.contains(&hash_phrase(&phrase, algorithm)) {
+                        f(perm);
+                    }
+                }
+            } else {
+                let phrase = words.join(" ");
+                if targets.contains(&hash_phrase(&phrase, algorithm)) {
+                    f(words);
+                }
+            }
+        });
+    }
+
+    /// Like `find_anagrams`, but scores each candidate set against `model`
+    /// and only yields the `top_n` highest-scoring sets, best first. If
+    /// `model` carries bigram data, the word ordering within each set is
+    /// chosen to maximize the bigram score; for sets of more than
+    /// `MAX_BIGRAM_PERMUTE_WORDS` words it is chosen greedily and is not
+    /// guaranteed to be the maximum.
+    pub fn find_anagrams_ranked<F: FnMut(Vec<Box<str>>, f64)>(
+        self,
+        word: &[u8],
+        minwords: usize,
+        maxwords: usize,
+        model: &LanguageModel,
+        top_n: usize,
+        mut f: F,
+    ) {
+        let mut heap: BinaryHeap<Reverse<ScoredSet>> = BinaryHeap::new();
+
+        self.find_anagrams(word, minwords, maxwords, AnagramType::Exact, |set| {
+            let words: Vec<&str> = set.iter().cloned().rev().collect();
+            let (score, order) = model.score_set(&words);
+            let words: Vec<Box<str>> = order.into_iter().map(|i| Box::from(words[i])).collect();
+
+            heap.push(Reverse(ScoredSet { score, words }));
+            if heap.len() > top_n {
+                heap.pop();
+            }
+        });
+
+        let mut results: Vec<ScoredSet> = heap.into_iter().map(|Reverse(scored)| scored).collect();
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+
+        for scored in results {
+            f(scored.words, scored.score);
+        }
     }
 
-    fn anagrams_recur(&self, mut dictionary_iter: Iter, pool: &[u8], minwords: usize, maxwords: usize, f: &mut dyn FnMut(Vec<&str>)) {
+    fn anagrams_recur(
+        &self,
+        ctx: SearchContext,
+        start: usize,
+        pool: &Key,
+        minwords: usize,
+        maxwords: usize,
+        f: &mut dyn FnMut(Vec<&str>),
+    ) {
         if minwords > maxwords {
             return
         }
 
-        if pool.len() == 0 && minwords == 0 {
+        let pool_len = key_len(pool);
+        let complete = match ctx.anagram_type {
+            AnagramType::Loose { .. } => ctx.total_letters - pool_len >= ctx.min_letters_used,
+            AnagramType::Exact | AnagramType::Proper { .. } => pool_len == 0,
+        };
+
+        if minwords == 0 && complete {
             f(vec![]);
-            return
         }
 
-        if maxwords == 0 {
+        if pool_len == 0 || maxwords == 0 {
             return
         }
 
-        if maxwords == 1 {
-            if let Some((key, words)) = self.dictionary.get_key_value(pool) {
-                let opt = dictionary_iter.next();
-                if opt.is_none() { return }
-                let (next_key, _) = opt.unwrap();
-
+        // The "exactly one word left, and it must exhaust the pool" fast
+        // path only applies when a complete answer has to use every
+        // remaining letter; `Loose` allows leaving letters unused, so it
+        // always falls through to the general walk below instead.
+        if maxwords == 1 && !matches!(ctx.anagram_type, AnagramType::Loose { .. }) {
+            if let Some(&key_idx) = ctx.key_index.get(pool) {
                 // Make sure to skip any words that we've already searched
                 // to avoid permutations of the same anagram
-                if next_key as *const _ > key as *const _ { return }
+                if key_idx < start { return }
 
-                for word in words {
+                let (_, words) = &ctx.entries[key_idx];
+                for word in words.iter() {
                     f(vec![word]);
                 }
             }
             return
         }
 
-        while let Some((key, words)) = dictionary_iter.next() {
+        for idx in start..ctx.entries.len() {
+            let (key, words) = &ctx.entries[idx];
             if let Some(new_pool) = subtract(key, pool) {
                 let new_minwords = if minwords == 0 { 0 } else { minwords - 1 };
-                self.anagrams_recur(dictionary_iter.clone(), &new_pool, new_minwords, maxwords - 1, &mut |set| {
-                    for word in words {
+                self.anagrams_recur(ctx, idx + 1, &new_pool, new_minwords, maxwords - 1, &mut |set| {
+                    for word in words.iter() {
                         let mut new_set = set.clone();
                         new_set.push(word);
                         f(new_set);
@@ -161,6 +524,182 @@ impl Anagrammer {
     }
 }
 
+// Above this many words, brute-forcing every ordering for bigram scoring
+// (factorial in the word count) is no longer practical; `score_set` falls
+// back to a greedy ordering instead.
+const MAX_BIGRAM_PERMUTE_WORDS: usize = 8;
+
+/// Log-probability of one word following another, keyed by (first, second).
+type BigramTable = HashMap<(Box<str>, Box<str>), f64>;
+
+/// A unigram (and optionally bigram) log-probability model used to rank
+/// candidate anagrams by plausibility, loaded from tab-separated text files
+/// alongside the dictionary (`word<TAB>logprob` per line, or
+/// `word1<TAB>word2<TAB>logprob` for bigrams).
+pub struct LanguageModel {
+    unigram: HashMap<Box<str>, f64>,
+    bigram: Option<BigramTable>,
+    total_tokens: f64,
+}
+
+impl LanguageModel {
+    pub fn from_unigram_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let mut unigram = HashMap::new();
+        let mut total_tokens: f64 = 0.0;
+
+        for line in contents.split('\n') {
+            let line = line.trim();
+            if line.len() == 0 { continue }
+
+            let mut parts = line.splitn(2, '\t');
+            let word = match parts.next() { Some(w) => w, None => continue };
+            let logprob: f64 = match parts.next().and_then(|p| p.trim().parse().ok()) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            unigram.insert(transliterate(word).to_lowercase().into_boxed_str(), logprob);
+            total_tokens += 1.0;
+        }
+
+        Ok(LanguageModel { unigram, bigram: None, total_tokens: total_tokens.max(1.0) })
+    }
+
+    pub fn with_bigram_path<P: AsRef<Path>>(mut self, path: P) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let mut bigram = HashMap::new();
+
+        for line in contents.split('\n') {
+            let line = line.trim();
+            if line.len() == 0 { continue }
+
+            let mut parts = line.splitn(3, '\t');
+            let first = match parts.next() { Some(w) => w, None => continue };
+            let second = match parts.next() { Some(w) => w, None => continue };
+            let logprob: f64 = match parts.next().and_then(|p| p.trim().parse().ok()) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let key = (transliterate(first).to_lowercase().into_boxed_str(), transliterate(second).to_lowercase().into_boxed_str());
+            bigram.insert(key, logprob);
+        }
+
+        self.bigram = Some(bigram);
+        Ok(self)
+    }
+
+    // A word missing from the unigram model is penalized as if it were a
+    // rare token: roughly as likely as one in ten occurrences of the
+    // rarest-plausible token of its length.
+    fn unigram_logprob(&self, word: &str) -> f64 {
+        let word = word.to_lowercase();
+        match self.unigram.get(word.as_str()) {
+            Some(&logprob) => logprob,
+            None => (10.0 / (self.total_tokens * 10f64.powi(word.len() as i32))).ln(),
+        }
+    }
+
+    fn bigram_logprob(&self, bigram: &BigramTable, first: &str, second: &str) -> f64 {
+        let key = (first.to_lowercase().into_boxed_str(), second.to_lowercase().into_boxed_str());
+        bigram.get(&key).cloned().unwrap_or(0.0)
+    }
+
+    // Scores a candidate word set, returning the total log-probability and
+    // the index ordering (into `words`) that earned it -- the natural order
+    // if there's no bigram model, otherwise whichever ordering maximizes the
+    // sum of consecutive-pair bigram scores (for up to
+    // `MAX_BIGRAM_PERMUTE_WORDS` words; above that, a greedy ordering).
+    fn score_set(&self, words: &[&str]) -> (f64, Vec<usize>) {
+        let unigram_sum: f64 = words.iter().map(|w| self.unigram_logprob(w)).sum();
+
+        let bigram = match self.bigram {
+            Some(ref bigram) => bigram,
+            None => return (unigram_sum, (0..words.len()).collect()),
+        };
+
+        let indices: Vec<usize> = (0..words.len()).collect();
+
+        let order = if indices.len() <= MAX_BIGRAM_PERMUTE_WORDS {
+            // Small enough to brute-force: try every ordering and keep the
+            // one with the highest bigram score.
+            let mut best_order = indices.clone();
+            let mut best_score = f64::NEG_INFINITY;
+
+            for order in permutations(&indices) {
+                let score: f64 = order.windows(2)
+                    .map(|pair| self.bigram_logprob(bigram, words[pair[0]], words[pair[1]]))
+                    .sum();
+
+                if score > best_score {
+                    best_score = score;
+                    best_order = order;
+                }
+            }
+
+            best_order
+        } else {
+            // `indices.len()!` is infeasible past a handful of words, so
+            // fall back to a greedy nearest-neighbor chain: repeatedly
+            // append whichever remaining word bigrams best with the last
+            // word placed so far. Not guaranteed optimal, but O(n^2) and
+            // always terminates.
+            let mut remaining = indices.clone();
+            let mut order = vec![remaining.remove(0)];
+
+            while !remaining.is_empty() {
+                let last = *order.last().unwrap();
+                let (pos, _) = remaining.iter().enumerate()
+                    .map(|(i, &next)| (i, self.bigram_logprob(bigram, words[last], words[next])))
+                    .fold((0, f64::NEG_INFINITY), |best, candidate| {
+                        if candidate.1 > best.1 { candidate } else { best }
+                    });
+                order.push(remaining.remove(pos));
+            }
+
+            order
+        };
+
+        let score: f64 = order.windows(2)
+            .map(|pair| self.bigram_logprob(bigram, words[pair[0]], words[pair[1]]))
+            .sum();
+
+        (unigram_sum + score, order)
+    }
+}
+
+struct ScoredSet {
+    score: f64,
+    words: Vec<Box<str>>,
+}
+
+impl PartialEq for ScoredSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+
+impl Eq for ScoredSet {}
+
+impl PartialOrd for ScoredSet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredSet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score.total_cmp(&other.score)
+    }
+}
+
 fn print_set(set: Vec<&str>) {
     let mut first = true;
     for item in set.iter().rev() {
@@ -173,8 +712,29 @@ fn print_set(set: Vec<&str>) {
     println!("");
 }
 
+fn print_ordered_set(set: Vec<&str>) {
+    println!("{}", set.join(" "));
+}
+
+fn print_ranked_set(set: Vec<Box<str>>, score: f64) {
+    let words: Vec<&str> = set.iter().map(|w| w.as_ref()).collect();
+    println!("{:.3}\t{}", score, words.join(" "));
+}
+
+fn print_owned_set(set: &[Box<str>]) {
+    let mut first = true;
+    for item in set.iter().rev() {
+        if !first {
+            print!(" ");
+        }
+        print!("{}", item);
+        first = false;
+    }
+    println!("");
+}
+
 fn main() -> std::io::Result<()> {
-    use argparse::{ArgumentParser, Store};
+    use argparse::{ArgumentParser, Store, StoreTrue};
 
     let (mut minwords, mut maxwords) = (0, std::usize::MAX);
     let (mut minletters, mut maxletters) = (0, std::usize::MAX);
@@ -183,6 +743,18 @@ fn main() -> std::io::Result<()> {
 
     let mut string = String::new();
 
+    let mut target_hashes = String::new();
+    let mut hash_algorithm = String::from("md5");
+    let mut permute = false;
+    let mut parallel = false;
+
+    let mut unigram_model_path = String::new();
+    let mut bigram_model_path = String::new();
+    let mut top_n = 20usize;
+
+    let mut anagram_type = String::from("exact");
+    let mut min_letters_used = 0usize;
+
     {
         let mut ap = ArgumentParser::new();
         ap.set_description("Find anagrams of the given string");
@@ -199,13 +771,38 @@ fn main() -> std::io::Result<()> {
             .add_option(&["-L", "--max-words"], Store, "The maximum number of letters per word in the generated anagrams");
         ap.refer(&mut dictionary_path)
             .add_option(&["-f", "--dictionary"], Store, "The path of the word list");
+        ap.refer(&mut target_hashes)
+            .add_option(&["--hashes"], Store, "Comma-separated hex digests to match rendered phrases against");
+        ap.refer(&mut hash_algorithm)
+            .add_option(&["--hash-algorithm"], Store, "The hash algorithm to use with --hashes (md5 or sha256)");
+        ap.refer(&mut permute)
+            .add_option(&["--permute"], StoreTrue, "With --hashes, also try reordering each candidate set's words (bounded; see find_anagrams_matching_hashes)");
+        ap.refer(&mut parallel)
+            .add_option(&["--parallel"], StoreTrue, "Search the dictionary across multiple threads (only with --type exact; ignored with --hashes or --unigram-model)");
+        ap.refer(&mut unigram_model_path)
+            .add_option(&["--unigram-model"], Store, "Rank results by plausibility using a unigram log-probability model at this path");
+        ap.refer(&mut bigram_model_path)
+            .add_option(&["--bigram-model"], Store, "With --unigram-model, also order each result's words using a bigram log-probability model at this path");
+        ap.refer(&mut top_n)
+            .add_option(&["--top"], Store, "With --unigram-model, the number of top-ranked results to print");
+        ap.refer(&mut anagram_type)
+            .add_option(&["--type"], Store, "Kind of anagram to search for: exact, proper (excludes re-emitting the input itself), or loose (allows unused letters)");
+        ap.refer(&mut min_letters_used)
+            .add_option(&["--min-letters-used"], Store, "With --type loose, the minimum number of letters a result must use");
         ap.parse_args_or_exit();
     }
 
-    let bytes = make_key(&string).unwrap_or_else(|| {
-        eprintln!("error: only ASCII strings are supported");
-        std::process::exit(1)
-    });
+    let bytes = normalized_bytes(&string);
+
+    let anagram_type = match anagram_type.as_str() {
+        "exact" => AnagramType::Exact,
+        "proper" => AnagramType::Proper { query: &string },
+        "loose" => AnagramType::Loose { min_letters_used },
+        other => {
+            eprintln!("error: unknown anagram type '{}'", other);
+            std::process::exit(1)
+        },
+    };
 
     let mut anagrammer = if dictionary_path.len() == 0 {
         Anagrammer::from_default_list()
@@ -217,6 +814,36 @@ fn main() -> std::io::Result<()> {
         anagrammer.restrict_letters(minletters, maxletters);
     }
 
-    anagrammer.find_anagrams(&bytes, minwords, maxwords, &mut print_set);
+    if unigram_model_path.len() != 0 {
+        let mut model = LanguageModel::from_unigram_path(&unigram_model_path)?;
+        if bigram_model_path.len() != 0 {
+            model = model.with_bigram_path(&bigram_model_path)?;
+        }
+
+        anagrammer.find_anagrams_ranked(&bytes, minwords, maxwords, &model, top_n, &mut print_ranked_set);
+    } else if target_hashes.len() == 0 {
+        // `find_anagrams_parallel` only implements `Exact` search; fall back
+        // to the serial path for `--type proper`/`--type loose`.
+        if parallel && anagram_type == AnagramType::Exact {
+            for set in anagrammer.find_anagrams_parallel(&bytes, minwords, maxwords) {
+                print_owned_set(&set);
+            }
+        } else {
+            anagrammer.find_anagrams(&bytes, minwords, maxwords, anagram_type, &mut print_set);
+        }
+    } else {
+        let targets: Vec<String> = target_hashes.split(',').map(|s| s.trim().to_string()).collect();
+        let algorithm = match hash_algorithm.as_str() {
+            "sha256" => HashAlgorithm::Sha256,
+            "md5" => HashAlgorithm::Md5,
+            other => {
+                eprintln!("error: unknown hash algorithm '{}'", other);
+                std::process::exit(1)
+            },
+        };
+
+        let hash_match = HashMatch { targets: &targets, algorithm, try_permutations: permute };
+        anagrammer.find_anagrams_matching_hashes(&bytes, minwords, maxwords, hash_match, &mut print_ordered_set);
+    }
     Ok(())
 }